@@ -2,9 +2,20 @@
 //!
 //! The cli module is used for setting up the command line app and parsing the arguments.
 
+use crate::error::RivError;
 use clap::{App, Arg};
 use glob::glob;
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Backend selects the surface images are rendered to.
+pub enum Backend {
+    /// Sdl2 blits the image into a resizable software window
+    Sdl2,
+    /// Sixel decodes the image and emits a sixel escape sequence to the terminal
+    Sixel,
+}
 
 /// Args contains the arguments that have been successfully parsed by the clap cli app
 pub struct Args {
@@ -12,10 +23,29 @@ pub struct Args {
     pub files: Vec<PathBuf>,
     /// dest_folder is the supplied or default folder for moving files
     pub dest_folder: PathBuf,
+    /// backend is the rendering backend selected on the command line
+    pub backend: Backend,
+    /// bins maps a key to the folder that key moves the current image into
+    pub bins: HashMap<char, PathBuf>,
+    /// recursive requests a `**` glob and a recursive filesystem watch
+    pub recursive: bool,
+    /// watch_root is the directory the filesystem watcher should observe
+    pub watch_root: PathBuf,
+}
+
+/// is_supported reports whether `path` carries one of the image extensions `riv` advertises.
+pub fn is_supported(path: &Path) -> bool {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) => matches!(
+            ext.to_lowercase().as_str(),
+            "jpg" | "jpeg" | "png" | "bmp" | "webp"
+        ),
+        None => false,
+    }
 }
 
 /// cli sets up the command line app and parses the arguments, using clap.
-pub fn cli() -> Result<Args, String> {
+pub fn cli() -> Result<Args, RivError> {
     let mut files = Vec::new();
     let matches = App::new("clive")
         .version("0.1.0")
@@ -34,27 +64,46 @@ pub fn cli() -> Result<Args, String> {
                 .help("Desintation folder for moving files to")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("backend")
+                .default_value("sdl2")
+                .long("backend")
+                .possible_values(&["sdl2", "sixel"])
+                .help("Rendering backend: an sdl2 window or sixel terminal output")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("bin")
+                .long("bin")
+                .help("Bind a key to a destination folder, e.g. --bin a=./animals")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("recursive")
+                .short("r")
+                .long("recursive")
+                .help("Search sub-directories and watch them for changes"),
+        )
         .get_matches();
     let glob_value = match matches.value_of("path") {
         Some(v) => v,
-        None => return Err("Failed to determine glob value".to_string()),
+        None => return Err(invalid_arg("Failed to determine glob value")),
     };
-    let glob_matches = glob(glob_value).map_err(|e| e.to_string())?;
+    let recursive = matches.is_present("recursive");
+    let watch_root = glob_root(glob_value);
+    let pattern = if recursive {
+        format!("{}/**/*", watch_root.display())
+    } else {
+        glob_value.to_string()
+    };
+    let glob_matches = glob(&pattern)?;
     for path in glob_matches {
         match path {
             Ok(p) => {
-                if let Some(ext) = p.extension() {
-                    if let Some(ext_str) = ext.to_str() {
-                        let low = ext_str.to_string().to_lowercase();
-                        if low == "jpg"
-                            || low == "jpeg"
-                            || low == "png"
-                            || low == "bmp"
-                            || low == "webp"
-                        {
-                            files.push(p)
-                        }
-                    }
+                if is_supported(&p) {
+                    files.push(p)
                 }
             }
             Err(e) => eprintln!("{}", e),
@@ -62,7 +111,62 @@ pub fn cli() -> Result<Args, String> {
     }
     let dest_folder = match matches.value_of("dest-folder") {
         Some(f) => PathBuf::from(f),
-        None => return Err("failed to determine destintation folder".to_string()),
+        None => return Err(invalid_arg("failed to determine destintation folder")),
+    };
+    let backend = match matches.value_of("backend") {
+        Some("sixel") => Backend::Sixel,
+        _ => Backend::Sdl2,
     };
-    Ok(Args { files, dest_folder })
+    let mut bins = HashMap::new();
+    if let Some(values) = matches.values_of("bin") {
+        for value in values {
+            let mut parts = value.splitn(2, '=');
+            let key = match parts.next().and_then(|k| k.chars().next()) {
+                Some(k) => k,
+                None => return Err(invalid_arg(&format!("invalid bin mapping '{}'", value))),
+            };
+            let folder = match parts.next() {
+                Some(f) if !f.is_empty() => PathBuf::from(f),
+                _ => {
+                    return Err(invalid_arg(&format!(
+                        "bin '{}' is missing a destination folder",
+                        value
+                    )))
+                }
+            };
+            bins.insert(key, folder);
+        }
+    }
+    Ok(Args {
+        files,
+        dest_folder,
+        backend,
+        bins,
+        recursive,
+        watch_root,
+    })
+}
+
+/// invalid_arg wraps a command line validation message as an io `InvalidInput` error.
+fn invalid_arg(message: &str) -> RivError {
+    RivError::Io(io::Error::new(io::ErrorKind::InvalidInput, message.to_string()))
+}
+
+/// glob_root returns the leading directory of a glob pattern, i.e. the longest prefix of path
+/// components that carry no glob metacharacters, falling back to the current directory. It is used
+/// both to anchor the recursive `**` glob and to point the watcher at the right directory.
+fn glob_root(pattern: &str) -> PathBuf {
+    let mut root = PathBuf::new();
+    for component in Path::new(pattern).components() {
+        let part = component.as_os_str().to_string_lossy();
+        if part.contains(['*', '?', '[', ']', '{', '}']) {
+            break;
+        }
+        root.push(component);
+    }
+    if root.as_os_str().is_empty() {
+        PathBuf::from(".")
+    } else {
+        root
+    }
 }
@@ -0,0 +1,92 @@
+//! # Decode
+//!
+//! The decode module reads images off disk into tightly packed RGBA8 buffers that SDL can upload
+//! directly, normalising awkward colour types (16-bit, grayscale, grayscale-alpha) that
+//! SDL2_image silently fails on. Animated PNGs are split into individual frames; every other
+//! format, including animated GIF and WebP, is decoded to a single frame.
+
+use std::fs::File;
+use std::path::Path;
+
+/// Frame is a single decoded frame as a tightly packed RGBA8 buffer.
+pub struct Frame {
+    /// width is the frame width in pixels
+    pub width: u32,
+    /// height is the frame height in pixels
+    pub height: u32,
+    /// pixels is the frame contents as `width * height` RGBA8 quads
+    pub pixels: Vec<u8>,
+}
+
+/// decode reads the image at `path`, normalising every frame to RGBA8.
+pub fn decode(path: &Path) -> Result<Vec<Frame>, String> {
+    match path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+    {
+        Some(ext) if ext == "png" => decode_png(path),
+        _ => decode_image(path),
+    }
+}
+
+/// decode_png reads a (possibly animated) PNG with the `png` crate, normalising 16-bit samples to
+/// 8-bit and expanding grayscale variants to RGB/RGBA by replicating the luma channel.
+fn decode_png(path: &Path) -> Result<Vec<Frame>, String> {
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let mut decoder = png::Decoder::new(file);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(|e| e.to_string())?;
+    let frame_count = reader
+        .info()
+        .animation_control()
+        .map(|a| a.num_frames)
+        .unwrap_or(1);
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let mut frames = Vec::new();
+    while (frames.len() as u32) < frame_count {
+        let info = match reader.next_frame(&mut buf) {
+            Ok(info) => info,
+            Err(e) => return Err(e.to_string()),
+        };
+        let data = &buf[..info.buffer_size()];
+        frames.push(Frame {
+            width: info.width,
+            height: info.height,
+            pixels: expand(data, info.color_type),
+        });
+    }
+    Ok(frames)
+}
+
+/// expand turns a normalised 8-bit PNG row buffer of the given colour type into RGBA8.
+fn expand(data: &[u8], color_type: png::ColorType) -> Vec<u8> {
+    match color_type {
+        png::ColorType::Grayscale => data
+            .iter()
+            .flat_map(|&luma| [luma, luma, luma, 255])
+            .collect(),
+        png::ColorType::GrayscaleAlpha => data
+            .chunks_exact(2)
+            .flat_map(|ga| [ga[0], ga[0], ga[0], ga[1]])
+            .collect(),
+        png::ColorType::Rgb => data
+            .chunks_exact(3)
+            .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 255])
+            .collect(),
+        png::ColorType::Rgba => data.to_vec(),
+        // normalize_to_color8 expands palette entries to RGB, so Indexed should not reach here.
+        png::ColorType::Indexed => data.to_vec(),
+    }
+}
+
+/// decode_image reads everything the `png` path does not through the `image` crate, flattening to
+/// a single RGBA8 frame.
+fn decode_image(path: &Path) -> Result<Vec<Frame>, String> {
+    let image = image::open(path).map_err(|e| e.to_string())?.to_rgba8();
+    Ok(vec![Frame {
+        width: image.width(),
+        height: image.height(),
+        pixels: image.into_raw(),
+    }])
+}
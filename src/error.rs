@@ -0,0 +1,95 @@
+//! # Error
+//!
+//! The error module defines `RivError`, the error type returned by the cli, program and renderer
+//! layers (the decode module reports its failures as strings), so a failure
+//! carries its source and enough context to say *which* file failed and *why* rather than a
+//! flattened string.
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+/// RivError is the error type returned by the cli and program layers.
+#[derive(Debug)]
+pub enum RivError {
+    /// Io wraps a filesystem or terminal io failure
+    Io(io::Error),
+    /// Sdl wraps an error reported by SDL, which surfaces failures as strings
+    Sdl(String),
+    /// Glob wraps an invalid glob pattern supplied on the command line
+    Glob(glob::PatternError),
+    /// NoImages is returned when the supplied glob matches no viewable images
+    NoImages,
+    /// Watch wraps a failure from the filesystem watcher
+    Watch(notify::Error),
+    /// MoveFailed records the image that could not be relocated and the underlying cause
+    MoveFailed {
+        /// path is the image that failed to move
+        path: PathBuf,
+        /// source is the underlying `fs_extra` failure
+        source: fs_extra::error::Error,
+    },
+}
+
+impl fmt::Display for RivError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RivError::Io(e) => write!(f, "io error: {}", e),
+            RivError::Sdl(e) => write!(f, "sdl error: {}", e),
+            RivError::Glob(e) => write!(f, "invalid glob pattern: {}", e),
+            RivError::NoImages => write!(f, "no images matched the supplied glob"),
+            RivError::Watch(e) => write!(f, "watcher error: {}", e),
+            RivError::MoveFailed { path, source } => {
+                write!(f, "failed to move {}: {}", path.display(), source)
+            }
+        }
+    }
+}
+
+impl Error for RivError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RivError::Io(e) => Some(e),
+            RivError::Glob(e) => Some(e),
+            RivError::Watch(e) => Some(e),
+            RivError::MoveFailed { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for RivError {
+    fn from(e: io::Error) -> Self {
+        RivError::Io(e)
+    }
+}
+
+impl From<glob::PatternError> for RivError {
+    fn from(e: glob::PatternError) -> Self {
+        RivError::Glob(e)
+    }
+}
+
+impl From<String> for RivError {
+    fn from(e: String) -> Self {
+        RivError::Sdl(e)
+    }
+}
+
+impl From<notify::Error> for RivError {
+    fn from(e: notify::Error) -> Self {
+        RivError::Watch(e)
+    }
+}
+
+impl From<fs_extra::error::Error> for RivError {
+    /// from wraps a bare `fs_extra` error as `MoveFailed`; the error names no image, so `path` is
+    /// left empty.
+    fn from(source: fs_extra::error::Error) -> Self {
+        RivError::MoveFailed {
+            path: PathBuf::new(),
+            source,
+        }
+    }
+}
@@ -0,0 +1,20 @@
+//! # riv
+//!
+//! riv is a simple command line image viewer that renders matching images to an sdl2 window or,
+//! over ssh and inside terminal multiplexers, straight into the terminal with sixel escape codes.
+
+mod cli;
+mod decode;
+mod error;
+mod program;
+mod renderer;
+mod ui;
+
+use program::Program;
+
+fn main() {
+    if let Err(e) = Program::init().and_then(|mut p| p.run()) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
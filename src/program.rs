@@ -3,203 +3,367 @@
 //! Program contains the program struct, which contains all information needed to run the
 //! event loop and render the images to screen
 
-use crate::cli;
-use crate::ui::{self, Action};
+use crate::cli::{self, Backend};
+use crate::error::RivError;
+use crate::renderer::{Renderer, Sdl2Renderer, SixelRenderer, View};
+use crate::ui::Action;
 use fs_extra::file::move_file;
-use sdl2::image::LoadTexture;
-use sdl2::rect::Rect;
-use sdl2::render::{TextureCreator, WindowCanvas};
-use sdl2::video::WindowContext;
-use sdl2::Sdl;
-use std::io::ErrorKind;
-use std::path::PathBuf;
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::collections::HashMap;
+use std::io::{self, ErrorKind};
+use std::path::{Component, Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
 use std::time::Duration;
 
 /// Program contains all information needed to run the event loop and render the images to screen
 pub struct Program {
-    sdl_context: Sdl,
-    canvas: WindowCanvas,
-    texture_creator: TextureCreator<WindowContext>,
+    renderer: Box<dyn Renderer>,
     images: Vec<PathBuf>,
     dest_folder: PathBuf,
+    bins: HashMap<char, PathBuf>,
+    recursive: bool,
+    watch_root: PathBuf,
+    /// undo_log records each move as (original path, new path, index) so it can be reversed
+    undo_log: Vec<(PathBuf, PathBuf, usize)>,
     index: usize,
-    ui_state: ui::State,
+    zoom: f32,
+    pan_x: i32,
+    pan_y: i32,
 }
 
+/// FsEvent is a filesystem change the watcher thread reports to the main loop.
+enum FsEvent {
+    /// Created is a newly written path
+    Created(PathBuf),
+    /// Removed is a path that has been deleted or moved away
+    Removed(PathBuf),
+}
+
+/// POLL_TIMEOUT bounds how long the main loop waits for input before draining the watcher channel.
+const POLL_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// ZOOM_STEP is the multiplier applied on each zoom in/out step.
+const ZOOM_STEP: f32 = 1.1;
+/// ZOOM_MIN and ZOOM_MAX bound the zoom factor to a sane range.
+const ZOOM_MIN: f32 = 0.1;
+const ZOOM_MAX: f32 = 16.0;
+/// PAN_STEP is the pixel distance a single pan action shifts the view.
+const PAN_STEP: i32 = 50;
+
 impl Program {
-    /// init scaffolds the program, by making a call to the cli module to parse the command line arguments,
-    /// sets up the sdl context, creates the window, the canvas and the texture creator.
-    pub fn init() -> Result<Program, String> {
+    /// init scaffolds the program, by making a call to the cli module to parse the command line
+    /// arguments and building the renderer backend selected on the command line.
+    pub fn init() -> Result<Program, RivError> {
         let args = cli::cli()?;
-        let images = args.files;
-        let dest_folder = args.dest_folder;
-        let sdl_context = sdl2::init()?;
-        let video = sdl_context.video()?;
-        let window = video
-            .window(
-                "rust-sdl2 demo: Video",
-                video.display_bounds(0).unwrap().width(),
-                video.display_bounds(0).unwrap().height(),
-            )
-            .position_centered()
-            .resizable()
-            .build()
-            .map_err(|e| e.to_string())?;
-
-        let canvas = window
-            .into_canvas()
-            .software()
-            .build()
-            .map_err(|e| e.to_string())?;
-        let texture_creator = canvas.texture_creator();
-        let ui_state = ui::State {
-            left_shift: false,
-            right_shift: false,
+        if args.files.is_empty() {
+            return Err(RivError::NoImages);
+        }
+        let renderer: Box<dyn Renderer> = match args.backend {
+            Backend::Sdl2 => Box::new(Sdl2Renderer::new()?),
+            Backend::Sixel => Box::new(SixelRenderer::new()?),
         };
         Ok(Program {
-            sdl_context,
-            canvas,
-            texture_creator,
-            images,
-            dest_folder,
+            renderer,
+            images: args.files,
+            dest_folder: args.dest_folder,
+            bins: args.bins,
+            recursive: args.recursive,
+            watch_root: args.watch_root,
+            undo_log: Vec::new(),
             index: 0,
-            ui_state,
+            zoom: 1.0,
+            pan_x: 0,
+            pan_y: 0,
         })
     }
 
-    /// render loads the image at the path in the images path vector located at the index and renders to screen
-    pub fn render(&mut self) -> Result<(), String> {
+    /// render draws the image at the path in the images vector located at the index to the backend
+    pub fn render(&mut self) -> Result<(), RivError> {
         if self.images.is_empty() {
             return Ok(());
         }
-        let texture = match self.texture_creator.load_texture(&self.images[self.index]) {
-            Ok(t) => t,
-            Err(e) => {
-                eprintln!("failed to render image {}", e);
-                return Ok(());
-            }
+        let view = View {
+            zoom: self.zoom,
+            pan_x: self.pan_x,
+            pan_y: self.pan_y,
         };
-        let query = texture.query();
-        let target = self.canvas.viewport();
-        let dest = make_dst(query.width, query.height, target.width(), target.height());
-        self.canvas.clear();
-        if let Err(e) = self.canvas.copy(&texture, None, dest) {
-            eprintln!("Failed to copy image to screen {}", e);
-            return Ok(());
-        }
-        self.canvas.present();
-        Ok(())
+        self.renderer.render(&self.images[self.index], &view)
+    }
+
+    /// clear_view restores the best-fit zoom and clears the pan offset, used when the image changes.
+    fn clear_view(&mut self) {
+        self.zoom = 1.0;
+        self.pan_x = 0;
+        self.pan_y = 0;
+    }
+
+    fn zoom_by(&mut self, factor: f32) -> Result<(), RivError> {
+        self.zoom = (self.zoom * factor).clamp(ZOOM_MIN, ZOOM_MAX);
+        self.render()
+    }
+
+    fn pan(&mut self, dx: i32, dy: i32) -> Result<(), RivError> {
+        self.pan_x += dx;
+        self.pan_y += dy;
+        self.render()
     }
 
-    fn increment(&mut self, step: usize) -> Result<(), String> {
+    fn reset_view(&mut self) -> Result<(), RivError> {
+        self.clear_view();
+        self.render()
+    }
+
+    fn next_frame(&mut self) -> Result<(), RivError> {
+        let view = View {
+            zoom: self.zoom,
+            pan_x: self.pan_x,
+            pan_y: self.pan_y,
+        };
+        self.renderer.next_frame(&view)
+    }
+
+    fn increment(&mut self, step: usize) -> Result<(), RivError> {
         if self.images.is_empty() || self.images.len() == 1 {
             return Ok(());
         }
         if self.index < self.images.len() - step {
             self.index += step;
         }
+        self.clear_view();
         self.render()
     }
 
-    fn decrement(&mut self, step: usize) -> Result<(), String> {
+    fn decrement(&mut self, step: usize) -> Result<(), RivError> {
         if self.index >= step {
             self.index -= step;
         }
+        self.clear_view();
         self.render()
     }
 
-    fn first(&mut self) -> Result<(), String> {
+    fn first(&mut self) -> Result<(), RivError> {
         self.index = 0;
+        self.clear_view();
         self.render()
     }
 
-    fn last(&mut self) -> Result<(), String> {
+    fn last(&mut self) -> Result<(), RivError> {
         if self.images.is_empty() {
             self.index = 0;
         } else {
             self.index = self.images.len() - 1;
         }
+        self.clear_view();
         self.render()
     }
 
-    fn move_image(&mut self) -> Result<(), String> {
-        match std::fs::create_dir_all(&self.dest_folder) {
+    fn move_image(&mut self) -> Result<(), RivError> {
+        let dest = self.dest_folder.clone();
+        self.move_to(&dest)
+    }
+
+    /// bin moves the current image into the folder bound to `key`, doing nothing if it is unbound.
+    fn bin(&mut self, key: char) -> Result<(), RivError> {
+        let dest = match self.bins.get(&key) {
+            Some(d) => d.clone(),
+            None => return Ok(()),
+        };
+        self.move_to(&dest)
+    }
+
+    /// move_to relocates the current image into `dest`, creating the folder on demand, advancing
+    /// the index with the same clamping used on deletion, and re-rendering.
+    fn move_to(&mut self, dest: &Path) -> Result<(), RivError> {
+        match std::fs::create_dir_all(dest) {
             Ok(_) => (),
             Err(e) => match e.kind() {
                 ErrorKind::AlreadyExists => (),
-                _ => return Err(e.to_string()),
+                _ => return Err(e.into()),
             },
         };
+        let from_index = self.index;
         let filepath = self.images.remove(self.index);
         if self.index >= self.images.len() && !self.images.is_empty() {
             self.index -= 1;
         }
         let filename = match filepath.file_name() {
             Some(f) => f,
-            None => return Err("failed to read filename for current image".to_string()),
+            None => {
+                return Err(RivError::Io(io::Error::new(
+                    ErrorKind::InvalidData,
+                    "failed to read filename for current image",
+                )))
+            }
         };
-        let newname = PathBuf::from(&self.dest_folder).join(filename);
+        let newname = dest.join(filename);
         let opt = &fs_extra::file::CopyOptions::new();
-        move_file(filepath, newname, opt).map_err(|e| e.to_string())?;
+        move_file(&filepath, &newname, opt).map_err(|source| RivError::MoveFailed {
+            path: filepath.clone(),
+            source,
+        })?;
+        self.undo_log.push((filepath, newname, from_index));
+        self.clear_view();
         self.render()
     }
 
-    /// run is the event loop that listens for input and delegates accordingly.
-    pub fn run(&mut self) -> Result<(), String> {
-        self.render()?;
-
-        'mainloop: loop {
-            for event in self.sdl_context.event_pump()?.poll_iter() {
-                match ui::event_action(&mut self.ui_state, &event) {
-                    Action::Quit => break 'mainloop,
-                    Action::ReRender => self.render()?,
-                    Action::Next => self.increment(1)?,
-                    Action::Prev => self.decrement(1)?,
-                    Action::Move => match self.move_image() {
-                        Ok(_) => (),
-                        Err(e) => eprintln!("Failed to move file: {}", e),
+    /// undo reverses the most recent move, recreating the original directory if it has since been
+    /// removed, restoring the image to its saved index and selecting it.
+    fn undo(&mut self) -> Result<(), RivError> {
+        let (original, current, index) = match self.undo_log.pop() {
+            Some(entry) => entry,
+            None => return Ok(()),
+        };
+        if let Some(parent) = original.parent() {
+            if !parent.as_os_str().is_empty() {
+                match std::fs::create_dir_all(parent) {
+                    Ok(_) => (),
+                    Err(e) => match e.kind() {
+                        ErrorKind::AlreadyExists => (),
+                        _ => return Err(e.into()),
                     },
-                    Action::First => self.first()?,
-                    Action::Last => self.last()?,
-                    Action::Noop => {}
                 }
             }
-            std::thread::sleep(Duration::from_millis(0));
         }
-
-        Ok(())
+        let opt = &fs_extra::file::CopyOptions::new();
+        move_file(&current, &original, opt).map_err(|source| RivError::MoveFailed {
+            path: current.clone(),
+            source,
+        })?;
+        let index = index.min(self.images.len());
+        self.images.insert(index, original);
+        self.index = index;
+        self.clear_view();
+        self.render()
     }
-}
 
-/// make dst determines the parameters of a rectangle required to place an image correctly in
-/// the window
-fn make_dst(src_x: u32, src_y: u32, dst_x: u32, dst_y: u32) -> Rect {
-    // case 1: both source dimensions smaller
-    if src_x < dst_x && src_y < dst_y {
-        return full_rect(src_x, src_y, dst_x, dst_y);
+    /// watch spawns a filesystem watcher over `watch_root`, returning the watcher (which
+    /// must be kept alive for events to flow) and the channel its changes arrive on.
+    fn watch(&self) -> Result<(impl Watcher, Receiver<FsEvent>), RivError> {
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+            let event = match res {
+                Ok(event) => event,
+                Err(_) => return,
+            };
+            let mapped = match event.kind {
+                EventKind::Create(_) => Some(FsEvent::Created),
+                EventKind::Remove(_) => Some(FsEvent::Removed),
+                _ => None,
+            };
+            if let Some(make) = mapped {
+                for path in event.paths {
+                    let _ = tx.send(make(path));
+                }
+            }
+        })?;
+        let mode = if self.recursive {
+            RecursiveMode::Recursive
+        } else {
+            RecursiveMode::NonRecursive
+        };
+        watcher.watch(&self.watch_root, mode)?;
+        Ok((watcher, rx))
     }
-    // case 2: source aspect ratio is larger
-    if src_x as f32 / src_y as f32 > dst_x as f32 / dst_y as f32 {
-        return fit_x_rect(src_x, src_y, dst_x, dst_y);
+
+    /// apply_fs_event folds a watcher change into `images`, keeping the allow-list and the same
+    /// index clamping used when moving a file, then redraws the current image.
+    fn apply_fs_event(&mut self, event: FsEvent) -> Result<(), RivError> {
+        match event {
+            FsEvent::Created(path) => {
+                if cli::is_supported(&path) && !self.images.iter().any(|i| paths_match(i, &path)) {
+                    self.images.push(path);
+                }
+            }
+            FsEvent::Removed(path) => {
+                if let Some(pos) = self.images.iter().position(|i| paths_match(i, &path)) {
+                    self.images.remove(pos);
+                    if pos < self.index {
+                        self.index -= 1;
+                    }
+                    if self.index >= self.images.len() && !self.images.is_empty() {
+                        self.index -= 1;
+                    }
+                }
+            }
+        }
+        self.render()
     }
-    // case 3: source aspect ratio is smaller
-    fit_y_rect(src_x, src_y, dst_x, dst_y)
-}
 
-fn full_rect(src_x: u32, src_y: u32, dst_x: u32, dst_y: u32) -> Rect {
-    let y = ((dst_y - src_y) as f32 / 2.0) as i32;
-    let x = ((dst_x - src_x) as f32 / 2.0) as i32;
-    Rect::new(x, y, src_x, src_y)
+    /// run is the event loop that listens for input and delegates accordingly.
+    pub fn run(&mut self) -> Result<(), RivError> {
+        self.render()?;
+        let (_watcher, fs_rx) = self.watch()?;
+
+        'mainloop: loop {
+            while let Ok(event) = fs_rx.try_recv() {
+                self.apply_fs_event(event)?;
+            }
+            let action = match self.renderer.poll_action(POLL_TIMEOUT)? {
+                Some(action) => action,
+                None => continue,
+            };
+            match action {
+                Action::Quit => break 'mainloop,
+                Action::ReRender => self.render()?,
+                Action::Next => self.increment(1)?,
+                Action::Prev => self.decrement(1)?,
+                Action::Move => match self.move_image() {
+                    Ok(_) => (),
+                    Err(e) => eprintln!("Failed to move file: {}", e),
+                },
+                Action::Bin(key) => match self.bin(key) {
+                    Ok(_) => (),
+                    Err(e) => eprintln!("Failed to move file: {}", e),
+                },
+                Action::ZoomIn => self.zoom_by(ZOOM_STEP)?,
+                Action::ZoomOut => self.zoom_by(1.0 / ZOOM_STEP)?,
+                Action::PanLeft => self.pan(-PAN_STEP, 0)?,
+                Action::PanRight => self.pan(PAN_STEP, 0)?,
+                Action::ResetView => self.reset_view()?,
+                Action::NextFrame => self.next_frame()?,
+                Action::Undo => match self.undo() {
+                    Ok(_) => (),
+                    Err(e) => eprintln!("Failed to undo: {}", e),
+                },
+                Action::First => self.first()?,
+                Action::Last => self.last()?,
+                Action::Noop => {}
+            }
+        }
+
+        Ok(())
+    }
 }
 
-fn fit_x_rect(src_x: u32, src_y: u32, dst_x: u32, dst_y: u32) -> Rect {
-    let height = ((src_y as f32 / src_x as f32) * dst_x as f32) as u32;
-    let y = ((dst_y - height) as f32 / 2.0) as i32;
-    Rect::new(0, y, dst_x, height)
+/// paths_match compares a tracked image against a watcher path by normalising both to absolute,
+/// lexically cleaned paths, so the relative vs absolute mismatch between the startup glob results
+/// and the paths `notify` reports is bridged without the false positives of suffix matching.
+fn paths_match(tracked: &Path, event: &Path) -> bool {
+    normalize(tracked) == normalize(event)
 }
 
-fn fit_y_rect(src_x: u32, src_y: u32, dst_x: u32, dst_y: u32) -> Rect {
-    let width = ((src_x as f32 / src_y as f32) * dst_y as f32) as u32;
-    let x = ((dst_x - width) as f32 / 2.0) as i32;
-    Rect::new(x, 0, width, dst_y)
+/// normalize resolves `path` to an absolute form, preferring the canonical path when it still
+/// exists on disk and otherwise cleaning `.`/`..` components against the current directory (a
+/// removed file can no longer be canonicalised).
+fn normalize(path: &Path) -> PathBuf {
+    if let Ok(canonical) = path.canonicalize() {
+        return canonical;
+    }
+    let base = std::env::current_dir().unwrap_or_default();
+    let joined = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        base.join(path)
+    };
+    let mut out = PathBuf::new();
+    for component in joined.components() {
+        match component {
+            Component::ParentDir => {
+                out.pop();
+            }
+            Component::CurDir => {}
+            other => out.push(other.as_os_str()),
+        }
+    }
+    out
 }
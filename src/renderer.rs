@@ -0,0 +1,359 @@
+//! # Renderer
+//!
+//! The renderer module abstracts the surface images are drawn to and the input source
+//! navigation is read from, so `riv` can either open an sdl2 window or paint directly into
+//! the terminal with sixel escape codes for use over ssh or inside a multiplexer.
+
+use crate::decode::{self, Frame};
+use crate::error::RivError;
+use crate::ui::{self, Action};
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::rect::Rect;
+use sdl2::render::{TextureCreator, WindowCanvas};
+use sdl2::video::WindowContext;
+use sdl2::{EventPump, Sdl};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+use termion::event::Key;
+use termion::input::{Keys, TermRead};
+use termion::raw::{IntoRawMode, RawTerminal};
+use termion::AsyncReader;
+
+/// View carries the zoom factor and pan offset applied on top of the best-fit base rectangle.
+pub struct View {
+    /// zoom scales the best-fit rectangle, with 1.0 being the unmodified fit
+    pub zoom: f32,
+    /// pan_x shifts the scaled rectangle horizontally in pixels
+    pub pan_x: i32,
+    /// pan_y shifts the scaled rectangle vertically in pixels
+    pub pan_y: i32,
+}
+
+/// Renderer is implemented by each drawing backend. It owns both the surface images are
+/// painted to and the input source navigation actions are read from.
+pub trait Renderer {
+    /// render draws the image at `path` to the backend's surface under the given `view`.
+    fn render(&mut self, path: &Path, view: &View) -> Result<(), RivError>;
+    /// poll_action waits up to `timeout` for an input, returning `None` if none arrived so the
+    /// caller can service other work (such as filesystem events) between key presses.
+    fn poll_action(&mut self, timeout: Duration) -> Result<Option<Action>, RivError>;
+    /// next_frame advances to the following frame of a multi-frame image and redraws it; backends
+    /// that do not decode frames leave this as a no-op.
+    fn next_frame(&mut self, _view: &View) -> Result<(), RivError> {
+        Ok(())
+    }
+}
+
+/// Sdl2Renderer blits images onto a resizable software window and reads the sdl event pump.
+pub struct Sdl2Renderer {
+    _sdl_context: Sdl,
+    canvas: WindowCanvas,
+    texture_creator: TextureCreator<WindowContext>,
+    event_pump: EventPump,
+    ui_state: ui::State,
+    current: Option<PathBuf>,
+    frames: Vec<Frame>,
+    frame: usize,
+}
+
+impl Sdl2Renderer {
+    /// new sets up the sdl context, the window, the canvas and the texture creator.
+    pub fn new() -> Result<Sdl2Renderer, RivError> {
+        let sdl_context = sdl2::init()?;
+        let video = sdl_context.video()?;
+        let window = video
+            .window(
+                "rust-sdl2 demo: Video",
+                video.display_bounds(0).unwrap().width(),
+                video.display_bounds(0).unwrap().height(),
+            )
+            .position_centered()
+            .resizable()
+            .build()
+            .map_err(|e| RivError::Sdl(e.to_string()))?;
+
+        let canvas = window
+            .into_canvas()
+            .software()
+            .build()
+            .map_err(|e| RivError::Sdl(e.to_string()))?;
+        let texture_creator = canvas.texture_creator();
+        let event_pump = sdl_context.event_pump()?;
+        let ui_state = ui::State {
+            left_shift: false,
+            right_shift: false,
+        };
+        Ok(Sdl2Renderer {
+            _sdl_context: sdl_context,
+            canvas,
+            texture_creator,
+            event_pump,
+            ui_state,
+            current: None,
+            frames: Vec::new(),
+            frame: 0,
+        })
+    }
+
+    /// present_frame uploads the current frame into a streaming texture and blits it under `view`.
+    fn present_frame(&mut self, view: &View) -> Result<(), RivError> {
+        let frame = match self.frames.get(self.frame) {
+            Some(f) => f,
+            None => return Ok(()),
+        };
+        let mut texture = self
+            .texture_creator
+            .create_texture_streaming(PixelFormatEnum::RGBA32, frame.width, frame.height)
+            .map_err(|e| RivError::Sdl(e.to_string()))?;
+        texture
+            .update(None, &frame.pixels, (frame.width * 4) as usize)
+            .map_err(|e| RivError::Sdl(e.to_string()))?;
+        let target = self.canvas.viewport();
+        let base = make_dst(frame.width, frame.height, target.width(), target.height());
+        let dest = apply_view(base, view);
+        self.canvas.clear();
+        if let Err(e) = self.canvas.copy(&texture, None, dest) {
+            eprintln!("Failed to copy image to screen {}", e);
+            return Ok(());
+        }
+        self.canvas.present();
+        Ok(())
+    }
+}
+
+impl Renderer for Sdl2Renderer {
+    fn render(&mut self, path: &Path, view: &View) -> Result<(), RivError> {
+        if self.current.as_deref() != Some(path) {
+            match decode::decode(path) {
+                Ok(frames) if !frames.is_empty() => {
+                    self.frames = frames;
+                    self.frame = 0;
+                    self.current = Some(path.to_path_buf());
+                }
+                Ok(_) => {
+                    eprintln!("no frames decoded for {}", path.display());
+                    return Ok(());
+                }
+                Err(e) => {
+                    eprintln!("failed to render image {}", e);
+                    return Ok(());
+                }
+            }
+        }
+        self.present_frame(view)
+    }
+
+    fn poll_action(&mut self, timeout: Duration) -> Result<Option<Action>, RivError> {
+        match self.event_pump.wait_event_timeout(timeout.as_millis() as u32) {
+            Some(event) => Ok(Some(ui::event_action(&mut self.ui_state, &event))),
+            None => Ok(None),
+        }
+    }
+
+    fn next_frame(&mut self, view: &View) -> Result<(), RivError> {
+        if self.frames.len() <= 1 {
+            return Ok(());
+        }
+        self.frame = (self.frame + 1) % self.frames.len();
+        self.present_frame(view)
+    }
+}
+
+/// SixelRenderer decodes the current image, downscales it to the terminal cell grid and emits
+/// a sixel escape sequence to stdout, reading navigation keys from the terminal in raw mode.
+/// The zoom and pan of the `View` are not applied: every image is drawn at its best fit.
+pub struct SixelRenderer {
+    stdout: RawTerminal<io::Stdout>,
+    keys: Keys<AsyncReader>,
+}
+
+/// CELL_WIDTH and CELL_HEIGHT are the pixel dimensions assumed for a single terminal cell when
+/// translating the reported column/row grid into a sixel pixel target.
+const CELL_WIDTH: u32 = 10;
+const CELL_HEIGHT: u32 = 20;
+
+impl SixelRenderer {
+    /// new puts the terminal into raw mode so key presses can be read without line buffering.
+    pub fn new() -> Result<SixelRenderer, RivError> {
+        let stdout = io::stdout().into_raw_mode()?;
+        let keys = termion::async_stdin().keys();
+        Ok(SixelRenderer { stdout, keys })
+    }
+}
+
+impl Renderer for SixelRenderer {
+    fn render(&mut self, path: &Path, _view: &View) -> Result<(), RivError> {
+        let (cols, rows) = termion::terminal_size()?;
+        let image = match image::open(path) {
+            Ok(i) => i.to_rgb8(),
+            Err(e) => {
+                eprintln!("failed to decode image {}", e);
+                return Ok(());
+            }
+        };
+        let (dst_w, dst_h) = fit(
+            image.width(),
+            image.height(),
+            cols as u32 * CELL_WIDTH,
+            rows as u32 * CELL_HEIGHT,
+        );
+        let scaled = image::imageops::resize(
+            &image,
+            dst_w.max(1),
+            dst_h.max(1),
+            image::imageops::FilterType::Triangle,
+        );
+        let sixel = encode_sixel(&scaled);
+        write!(self.stdout, "\x1b[2J\x1b[H{}", sixel)?;
+        self.stdout.flush()?;
+        Ok(())
+    }
+
+    fn poll_action(&mut self, timeout: Duration) -> Result<Option<Action>, RivError> {
+        let key = match self.keys.next() {
+            Some(Ok(k)) => k,
+            Some(Err(e)) => return Err(e.into()),
+            None => {
+                // async_stdin yields None when no key is buffered; nap so we do not busy-loop.
+                thread::sleep(timeout);
+                return Ok(None);
+            }
+        };
+        Ok(Some(match key {
+            Key::Esc | Key::Char('q') => Action::Quit,
+            Key::Right | Key::Char('l') => Action::Next,
+            Key::Left | Key::Char('h') => Action::Prev,
+            Key::Char('m') => Action::Move,
+            Key::Char('.') => Action::NextFrame,
+            Key::Char('u') => Action::Undo,
+            Key::Char('g') => Action::First,
+            Key::Char('G') => Action::Last,
+            Key::Char(c) if c.is_ascii_alphanumeric() => Action::Bin(c),
+            _ => Action::Noop,
+        }))
+    }
+}
+
+/// fit scales `(src_w, src_h)` down to fit inside `(max_w, max_h)` while preserving aspect ratio.
+fn fit(src_w: u32, src_h: u32, max_w: u32, max_h: u32) -> (u32, u32) {
+    if src_w <= max_w && src_h <= max_h {
+        return (src_w, src_h);
+    }
+    let scale = (max_w as f32 / src_w as f32).min(max_h as f32 / src_h as f32);
+    (
+        (src_w as f32 * scale) as u32,
+        (src_h as f32 * scale) as u32,
+    )
+}
+
+/// palette_index quantizes an RGB pixel onto a 6x6x6 colour cube, yielding an index in 0..216.
+fn palette_index(r: u8, g: u8, b: u8) -> u8 {
+    let q = |c: u8| (c as u32 * 5 / 255) as u8;
+    q(r) * 36 + q(g) * 6 + q(b)
+}
+
+/// encode_sixel turns an RGB image into a sixel escape sequence quantized to the 216-colour cube.
+fn encode_sixel(image: &image::RgbImage) -> String {
+    let (width, height) = image.dimensions();
+    let mut out = String::from("\x1bPq");
+    // Declare the colour registers of the 6x6x6 cube in sixel percent units.
+    for index in 0..216u16 {
+        let r = (index / 36) % 6;
+        let g = (index / 6) % 6;
+        let b = index % 6;
+        out.push_str(&format!(
+            "#{};2;{};{};{}",
+            index,
+            r * 20,
+            g * 20,
+            b * 20
+        ));
+    }
+    // Sixels encode six vertical pixels per band; emit one pass per colour present in the band.
+    let mut band = 0;
+    while band < height {
+        let mut colours = [false; 216];
+        for x in 0..width {
+            for bit in 0..6 {
+                let y = band + bit;
+                if y >= height {
+                    break;
+                }
+                let p = image.get_pixel(x, y);
+                colours[palette_index(p[0], p[1], p[2]) as usize] = true;
+            }
+        }
+        for (index, present) in colours.iter().enumerate() {
+            if !present {
+                continue;
+            }
+            out.push_str(&format!("#{}", index));
+            for x in 0..width {
+                let mut sixel = 0u8;
+                for bit in 0..6 {
+                    let y = band + bit;
+                    if y >= height {
+                        break;
+                    }
+                    let p = image.get_pixel(x, y);
+                    if palette_index(p[0], p[1], p[2]) as usize == index {
+                        sixel |= 1 << bit;
+                    }
+                }
+                out.push((sixel + 63) as char);
+            }
+            out.push('$');
+        }
+        out.push('-');
+        band += 6;
+    }
+    out.push_str("\x1b\\");
+    out
+}
+
+/// make dst determines the parameters of a rectangle required to place an image correctly in
+/// the window
+fn make_dst(src_x: u32, src_y: u32, dst_x: u32, dst_y: u32) -> Rect {
+    // case 1: both source dimensions smaller
+    if src_x < dst_x && src_y < dst_y {
+        return full_rect(src_x, src_y, dst_x, dst_y);
+    }
+    // case 2: source aspect ratio is larger
+    if src_x as f32 / src_y as f32 > dst_x as f32 / dst_y as f32 {
+        return fit_x_rect(src_x, src_y, dst_x, dst_y);
+    }
+    // case 3: source aspect ratio is smaller
+    fit_y_rect(src_x, src_y, dst_x, dst_y)
+}
+
+/// apply_view scales the best-fit `base` rectangle by the view's zoom about its own centre and
+/// then shifts it by the pan offset, keeping the magnification anchored to the viewport centre.
+fn apply_view(base: Rect, view: &View) -> Rect {
+    let bw = base.width() as f32;
+    let bh = base.height() as f32;
+    let w = bw * view.zoom;
+    let h = bh * view.zoom;
+    let x = base.x() - ((w - bw) / 2.0) as i32 + view.pan_x;
+    let y = base.y() - ((h - bh) / 2.0) as i32 + view.pan_y;
+    Rect::new(x, y, w as u32, h as u32)
+}
+
+fn full_rect(src_x: u32, src_y: u32, dst_x: u32, dst_y: u32) -> Rect {
+    let y = ((dst_y - src_y) as f32 / 2.0) as i32;
+    let x = ((dst_x - src_x) as f32 / 2.0) as i32;
+    Rect::new(x, y, src_x, src_y)
+}
+
+fn fit_x_rect(src_x: u32, src_y: u32, dst_x: u32, dst_y: u32) -> Rect {
+    let height = ((src_y as f32 / src_x as f32) * dst_x as f32) as u32;
+    let y = ((dst_y - height) as f32 / 2.0) as i32;
+    Rect::new(0, y, dst_x, height)
+}
+
+fn fit_y_rect(src_x: u32, src_y: u32, dst_x: u32, dst_y: u32) -> Rect {
+    let width = ((src_x as f32 / src_y as f32) * dst_y as f32) as u32;
+    let x = ((dst_x - width) as f32 / 2.0) as i32;
+    Rect::new(x, 0, width, dst_y)
+}
@@ -0,0 +1,118 @@
+//! # UI
+//!
+//! The ui module turns raw sdl2 events into the high level actions the program loop acts on,
+//! tracking the modifier keys that need to persist between events.
+
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+
+/// State tracks the modifier keys held across events so shifted navigation keeps working.
+pub struct State {
+    /// left_shift is true while the left shift key is held down
+    pub left_shift: bool,
+    /// right_shift is true while the right shift key is held down
+    pub right_shift: bool,
+}
+
+impl State {
+    /// shift reports whether either shift key is currently held.
+    pub fn shift(&self) -> bool {
+        self.left_shift || self.right_shift
+    }
+}
+
+/// Action is the high level command produced from an input event, understood by `Program::run`.
+pub enum Action {
+    /// Quit exits the event loop
+    Quit,
+    /// ReRender redraws the current image, e.g. after a window resize
+    ReRender,
+    /// Next advances to the following image
+    Next,
+    /// Prev steps back to the previous image
+    Prev,
+    /// Move relocates the current image into the destination folder
+    Move,
+    /// Bin relocates the current image into the folder bound to the given key
+    Bin(char),
+    /// ZoomIn magnifies the current image around the viewport centre
+    ZoomIn,
+    /// ZoomOut shrinks the current image around the viewport centre
+    ZoomOut,
+    /// PanLeft shifts the image towards the left of the window
+    PanLeft,
+    /// PanRight shifts the image towards the right of the window
+    PanRight,
+    /// ResetView restores the best-fit zoom and clears any pan
+    ResetView,
+    /// NextFrame steps to the following frame of a multi-frame image
+    NextFrame,
+    /// Undo reverses the most recent move operation
+    Undo,
+    /// First jumps to the first image
+    First,
+    /// Last jumps to the last image
+    Last,
+    /// Noop ignores the event
+    Noop,
+}
+
+/// event_action maps a single sdl2 event to an `Action`, updating `state` for modifier keys.
+pub fn event_action(state: &mut State, event: &Event) -> Action {
+    match event {
+        Event::Quit { .. } => Action::Quit,
+        Event::Window { .. } => Action::ReRender,
+        Event::MouseWheel { y, .. } if *y > 0 => Action::ZoomIn,
+        Event::MouseWheel { y, .. } if *y < 0 => Action::ZoomOut,
+        Event::KeyUp {
+            keycode: Some(Keycode::LShift),
+            ..
+        } => {
+            state.left_shift = false;
+            Action::Noop
+        }
+        Event::KeyUp {
+            keycode: Some(Keycode::RShift),
+            ..
+        } => {
+            state.right_shift = false;
+            Action::Noop
+        }
+        Event::KeyDown {
+            keycode: Some(keycode),
+            ..
+        } => key_action(state, *keycode),
+        _ => Action::Noop,
+    }
+}
+
+/// key_action resolves a pressed key into an `Action`, recording shift presses on `state`.
+fn key_action(state: &mut State, keycode: Keycode) -> Action {
+    match keycode {
+        Keycode::LShift => {
+            state.left_shift = true;
+            Action::Noop
+        }
+        Keycode::RShift => {
+            state.right_shift = true;
+            Action::Noop
+        }
+        Keycode::Escape | Keycode::Q => Action::Quit,
+        Keycode::L => Action::Next,
+        Keycode::H => Action::Prev,
+        Keycode::Right => Action::PanRight,
+        Keycode::Left => Action::PanLeft,
+        Keycode::Plus | Keycode::KpPlus | Keycode::Equals => Action::ZoomIn,
+        Keycode::Minus | Keycode::KpMinus => Action::ZoomOut,
+        Keycode::Num0 => Action::ResetView,
+        Keycode::Period => Action::NextFrame,
+        Keycode::U => Action::Undo,
+        Keycode::M => Action::Move,
+        Keycode::G if state.shift() => Action::Last,
+        Keycode::G => Action::First,
+        other => match char::from_u32(other as u32) {
+            Some(c) if c.is_ascii_alphanumeric() => Action::Bin(c),
+            _ => Action::Noop,
+        },
+    }
+}